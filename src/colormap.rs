@@ -0,0 +1,84 @@
+// Palettes for mapping a continuous escape value to a color.
+
+use egui::Color32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    Grayscale,
+    Hot,
+    Viridis,
+    Ultra,
+}
+
+impl Colormap {
+    pub const ALL: [Self; 4] = [Self::Grayscale, Self::Hot, Self::Viridis, Self::Ultra];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Grayscale => "Grayscale",
+            Self::Hot => "Hot",
+            Self::Viridis => "Viridis",
+            Self::Ultra => "Ultra",
+        }
+    }
+
+    // Matches the `palette()` selector in `shader.wgsl`.
+    pub fn shader_index(&self) -> i32 {
+        match self {
+            Self::Grayscale => 0,
+            Self::Hot => 1,
+            Self::Viridis => 2,
+            Self::Ultra => 3,
+        }
+    }
+
+    // Maps escape value `t` in `[0, 1]` to a color.
+    pub fn sample(&self, t: f64) -> Color32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Grayscale => {
+                let v = (t * 255.0) as u8;
+                Color32::from_rgb(v, v, v)
+            }
+            Self::Hot => {
+                let r = (t * 3.0).clamp(0.0, 1.0);
+                let g = (t * 3.0 - 1.0).clamp(0.0, 1.0);
+                let b = (t * 3.0 - 2.0).clamp(0.0, 1.0);
+                Color32::from_rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+            }
+            Self::Viridis => {
+                // Cheap polynomial approximation of the viridis colormap.
+                let r = 0.280 + t * (-0.023 + t * (0.335 - t * 0.236));
+                let g = 0.004 + t * (1.384 + t * (-0.916 + t * 0.414));
+                let b = 0.329 + t * (0.718 + t * (-1.306 + t * 0.590));
+                Color32::from_rgb(
+                    (r.clamp(0.0, 1.0) * 255.0) as u8,
+                    (g.clamp(0.0, 1.0) * 255.0) as u8,
+                    (b.clamp(0.0, 1.0) * 255.0) as u8,
+                )
+            }
+            Self::Ultra => {
+                // Bernstein-polynomial "ultra fractal" style palette.
+                let r = 9.0 * (1.0 - t) * t.powi(3);
+                let g = 15.0 * (1.0 - t).powi(2) * t.powi(2);
+                let b = 8.5 * (1.0 - t).powi(3) * t;
+                Color32::from_rgb(
+                    (r.clamp(0.0, 1.0) * 255.0) as u8,
+                    (g.clamp(0.0, 1.0) * 255.0) as u8,
+                    (b.clamp(0.0, 1.0) * 255.0) as u8,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_clamps_out_of_range_t() {
+        assert_eq!(Colormap::Ultra.sample(-1.0), Colormap::Ultra.sample(0.0));
+        assert_eq!(Colormap::Ultra.sample(2.0), Colormap::Ultra.sample(1.0));
+    }
+}
@@ -0,0 +1,161 @@
+// Optional GPU rendering path: an egui paint callback that runs the
+// escape-time iteration in a fragment shader instead of on the CPU.
+
+use eframe::egui_wgpu::{self, wgpu};
+use egui::PaintCallbackInfo;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    center: [f32; 2],
+    seed: [f32; 2],
+    zoom: f32,
+    iters: i32,
+    julia: i32,
+    colormap: i32,
+}
+
+// GPU resources for the shader-based renderer, owned by the egui_wgpu
+// render state.
+pub struct MandelbrotRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+}
+
+impl MandelbrotRenderer {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("mandelbrot_shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("mandelbrot_uniforms"),
+            size: std::mem::size_of::<Uniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("mandelbrot_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("mandelbrot_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("mandelbrot_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("mandelbrot_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            uniform_buffer,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_uniforms(
+        &self,
+        queue: &wgpu::Queue,
+        center: (f64, f64),
+        zoom: f64,
+        iters: u32,
+        seed: (f64, f64),
+        julia: bool,
+        colormap: i32,
+    ) {
+        let uniforms = Uniforms {
+            center: [center.0 as f32, center.1 as f32],
+            seed: [seed.0 as f32, seed.1 as f32],
+            zoom: zoom as f32,
+            iters: iters as i32,
+            julia: julia as i32,
+            colormap,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+}
+
+// Paint-callback payload: view state written into the uniform buffer before
+// the draw call.
+pub struct MandelbrotCallback {
+    pub center: (f64, f64),
+    pub zoom: f64,
+    pub iters: u32,
+    pub julia_seed: Option<(f64, f64)>,
+    pub colormap: i32,
+}
+
+impl egui_wgpu::CallbackTrait for MandelbrotCallback {
+    fn prepare(
+        &self,
+        _device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        _screen_descriptor: &egui_wgpu::ScreenDescriptor,
+        _egui_encoder: &mut wgpu::CommandEncoder,
+        resources: &mut egui_wgpu::CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        let renderer: &MandelbrotRenderer = resources.get().expect("MandelbrotRenderer registered");
+        renderer.write_uniforms(
+            queue,
+            self.center,
+            self.zoom,
+            self.iters,
+            self.julia_seed.unwrap_or((0.0, 0.0)),
+            self.julia_seed.is_some(),
+            self.colormap,
+        );
+        Vec::new()
+    }
+
+    fn paint<'a>(
+        &'a self,
+        _info: PaintCallbackInfo,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        resources: &'a egui_wgpu::CallbackResources,
+    ) {
+        let renderer: &MandelbrotRenderer = resources.get().expect("MandelbrotRenderer registered");
+        render_pass.set_pipeline(&renderer.pipeline);
+        render_pass.set_bind_group(0, &renderer.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
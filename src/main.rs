@@ -1,19 +1,84 @@
-use eframe::{App, Error, NativeOptions};
+mod colormap;
+mod gpu;
+mod progressive;
+
+use colormap::Colormap;
+use eframe::{egui_wgpu, App, Error, NativeOptions};
 use egui::{CentralPanel, Color32, ColorImage, Image, PointerButton, Pos2, Vec2, ViewportBuilder};
-use image::{ImageBuffer, Rgb};
+use gpu::{MandelbrotCallback, MandelbrotRenderer};
 use num_complex::Complex;
+use progressive::{ProgressiveRenderer, ViewParams};
+
+const DEFAULT_MAX_ITER: u32 = 256;
+// A couple of extra iterations past escape stabilize the smooth-coloring
+// estimate, since `mu` is only well-behaved once `|z|` is comfortably past
+// the bailout radius.
+const EXTRA_ITERS: u32 = 2;
+// `mu` cycles through the palette every this many steps. Dividing by
+// `max_iter` instead would crush almost the whole exterior into the
+// near-zero (near-black) end of every palette, and get worse the higher
+// `max_iter` is set.
+pub(crate) const COLOR_CYCLE: f64 = 32.0;
 
-const MAX_ITER: u32 = 256;
+/// The complex-plane region currently being rendered.
+#[derive(Clone, Copy)]
+pub(crate) struct Bounds {
+    pub(crate) x: (f64, f64),
+    pub(crate) y: (f64, f64),
+}
 
-fn mandelbrot(c: Complex<f64>) -> u32 {
-    let mut z = Complex::new(0., 0.);
-    for i in 0..MAX_ITER {
+/// Default seed used when switching into Julia mode for the first time.
+const DEFAULT_JULIA_SEED: Complex<f64> = Complex::new(-0.4, 0.6);
+
+/// Runs the escape-time iteration from `z0` under `z -> z*z + c` and returns
+/// the iteration count together with the final `z`, so callers can derive a
+/// smooth (continuous) escape value instead of just the raw integer count.
+pub(crate) fn escape_time(
+    mut z: Complex<f64>,
+    c: Complex<f64>,
+    max_iter: u32,
+) -> (u32, Complex<f64>) {
+    for i in 0..max_iter {
         if z.norm_sqr() > 4. {
-            return i;
+            for _ in 0..EXTRA_ITERS {
+                z = z * z + c;
+            }
+            return (i, z);
         }
         z = z * z + c;
     }
-    MAX_ITER
+    (max_iter, z)
+}
+
+/// Mandelbrot escape time for pixel coordinate `c`: starts at `z = 0` and
+/// iterates with `c` fixed to the pixel.
+pub(crate) fn mandelbrot(c: Complex<f64>, max_iter: u32) -> (u32, Complex<f64>) {
+    escape_time(Complex::new(0., 0.), c, max_iter)
+}
+
+/// Julia-set escape time for pixel coordinate `pixel`: starts at `z = pixel`
+/// and iterates with `c` fixed to the configured `seed`, reusing the same
+/// escape-time loop as the Mandelbrot set.
+pub(crate) fn julia(pixel: Complex<f64>, seed: Complex<f64>, max_iter: u32) -> (u32, Complex<f64>) {
+    escape_time(pixel, seed, max_iter)
+}
+
+/// Normalized (fractional) escape value used for smooth coloring, killing
+/// the harsh banding that plain `i % N` grayscale produces. Interior points
+/// that never escape return `None` and should be painted black.
+pub(crate) fn smooth_escape(i: u32, z: Complex<f64>, max_iter: u32) -> Option<f64> {
+    if i >= max_iter {
+        return None;
+    }
+    let mu = i as f64 + 1.0 - (z.norm().ln() / 2f64.ln()).ln() / 2f64.ln();
+    Some(mu)
+}
+
+/// Maps a smooth escape value to `[0, 1)` for palette sampling by cycling it
+/// every [`COLOR_CYCLE`] steps, independent of `max_iter`.
+pub(crate) fn color_phase(mu: f64) -> f64 {
+    let t = mu / COLOR_CYCLE;
+    t - t.floor()
 }
 
 struct MandelbrotApp {
@@ -26,8 +91,32 @@ struct MandelbrotApp {
     center_y: f64,
     dragging: bool,
     last_mouse_pos: Option<Pos2>,
+    use_gpu: bool,
+    gpu_available: bool,
+    colormap: Colormap,
+    image_rect: egui::Rect,
+    julia_mode: bool,
+    julia_seed: Option<Complex<f64>>,
+    last_seed_drag_pos: Option<Pos2>,
+    max_iter: u32,
+    last_frame_time: std::time::Duration,
+    renderer: ProgressiveRenderer,
+    back_buffer: ColorImage,
+    current_generation: u64,
+    tiles_received: usize,
+    tiles_expected: usize,
+    render_start: std::time::Instant,
+    dirty: bool,
+    last_submit: std::time::Instant,
 }
 
+/// Minimum time between successive render submissions while the view is
+/// changing continuously (dragging, seed-nudging). Without this, a 1-2
+/// second drag would queue a full tile fan-out on the rayon pool every
+/// single frame, backing it up behind abandoned work instead of keeping
+/// navigation responsive.
+const REGEN_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(100);
+
 impl MandelbrotApp {
     fn new(width: usize, height: usize) -> Self {
         Self {
@@ -40,36 +129,136 @@ impl MandelbrotApp {
             center_y: 0.,
             dragging: false,
             last_mouse_pos: None,
+            use_gpu: false,
+            gpu_available: false,
+            colormap: Colormap::Ultra,
+            image_rect: egui::Rect::from_min_size(
+                Pos2::ZERO,
+                Vec2::new(width as f32, height as f32),
+            ),
+            julia_mode: false,
+            julia_seed: None,
+            last_seed_drag_pos: None,
+            max_iter: DEFAULT_MAX_ITER,
+            last_frame_time: std::time::Duration::ZERO,
+            renderer: ProgressiveRenderer::new(),
+            back_buffer: ColorImage::new([width, height], Color32::BLACK),
+            current_generation: 0,
+            tiles_received: 0,
+            tiles_expected: 0,
+            render_start: std::time::Instant::now(),
+            dirty: false,
+            last_submit: std::time::Instant::now(),
+        }
+    }
+
+    /// Marks the view as changed without necessarily submitting a render
+    /// right away, so rapid continuous input (dragging, seed-nudging) is
+    /// coalesced into submissions at most once per [`REGEN_DEBOUNCE`]
+    /// instead of spawning a full tile fan-out every single frame.
+    fn request_regenerate(&mut self, ctx: &egui::Context) {
+        self.dirty = true;
+        if self.last_submit.elapsed() >= REGEN_DEBOUNCE {
+            self.regenerate();
+            self.dirty = false;
+            self.last_submit = std::time::Instant::now();
+        } else {
+            // Make sure the coalesced request still gets flushed once the
+            // debounce window passes, even if no further input arrives.
+            ctx.request_repaint_after(REGEN_DEBOUNCE - self.last_submit.elapsed());
         }
     }
 
-    fn generate_mandelbrot(&mut self) {
-        let width = self.width;
-        let height = self.height;
-        let zoom = self.zoom;
-        let center_x = self.center_x;
-        let center_y = self.center_y;
+    /// Flushes a pending debounced render, for use once per frame after all
+    /// input has been processed so the very last view before input stops is
+    /// never left un-submitted.
+    fn flush_pending_regenerate(&mut self) {
+        if self.dirty && self.last_submit.elapsed() >= REGEN_DEBOUNCE {
+            self.regenerate();
+            self.dirty = false;
+            self.last_submit = std::time::Instant::now();
+        }
+    }
 
-        let mut img_buf: ImageBuffer<Rgb<u8>, Vec<u8>> =
-            ImageBuffer::new(width as u32, height as u32);
+    /// Supersedes any in-flight render and schedules a new one for the current
+    /// view on the background thread pool, discarding the old back buffer.
+    /// The superseded render is not stopped: its tiles are still computed and
+    /// are dropped on arrival by `poll_renderer`.
+    fn regenerate(&mut self) {
+        self.back_buffer = ColorImage::new([self.width, self.height], Color32::BLACK);
+        self.render_start = std::time::Instant::now();
 
-        for (x, y, pixel) in img_buf.enumerate_pixels_mut() {
-            let x_val = center_x + (x as f64 - width as f64 / 2.0) / (width as f64 / 2.0) * zoom;
-            let y_val = center_y + (y as f64 - height as f64 / 2.0) / (height as f64 / 2.0) * zoom;
-            let c = Complex::new(x_val, y_val);
+        let julia_seed = self
+            .julia_mode
+            .then_some(self.julia_seed.unwrap_or(DEFAULT_JULIA_SEED));
+        let params = ViewParams {
+            width: self.width,
+            height: self.height,
+            bounds: self.bounds(),
+            colormap: self.colormap,
+            max_iter: self.max_iter,
+            julia_seed,
+        };
+        let (generation, tile_count) = self.renderer.submit(params);
+        self.current_generation = generation;
+        self.tiles_received = 0;
+        self.tiles_expected = tile_count;
+    }
 
-            let i = mandelbrot(c);
-            let color_value = (i % 256) as u8;
-            *pixel = Rgb([color_value, color_value, color_value]);
+    /// Applies any tiles that have arrived from the background workers to
+    /// the back buffer, discarding ones from a superseded generation; once
+    /// every tile for the current generation is in, swaps it into the front
+    /// buffer that's actually displayed.
+    fn poll_renderer(&mut self, ctx: &egui::Context) {
+        for tile in self.renderer.drain() {
+            if tile.generation != self.current_generation {
+                continue;
+            }
+            let start = tile.y_start * self.width;
+            let end = start + tile.rows * self.width;
+            self.back_buffer.pixels[start..end].copy_from_slice(&tile.pixels);
+            self.tiles_received += 1;
         }
 
-        // Convert the `image` crate's `ImageBuffer` to `egui::ColorImage`
-        let pixels = img_buf
-            .into_raw()
-            .chunks(3)
-            .map(|chunk| Color32::from_rgb(chunk[0], chunk[1], chunk[2]))
-            .collect::<Vec<_>>();
-        self.image.pixels = pixels;
+        if self.tiles_expected > 0 && self.tiles_received == self.tiles_expected {
+            self.image = std::mem::replace(
+                &mut self.back_buffer,
+                ColorImage::new([self.width, self.height], Color32::BLACK),
+            );
+            self.texture_handle = None; // Invalidate the texture
+            self.last_frame_time = self.render_start.elapsed();
+            self.tiles_expected = 0;
+        } else if self.tiles_expected > 0 {
+            // Still filling in the back buffer: keep polling next frame.
+            ctx.request_repaint();
+        }
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds {
+            x: (self.center_x - self.zoom, self.center_x + self.zoom),
+            y: (self.center_y - self.zoom, self.center_y + self.zoom),
+        }
+    }
+
+    /// Zooms by `factor` while keeping the complex coordinate under `pointer`
+    /// fixed on screen, so the feature under the cursor doesn't drift as the
+    /// user scrolls (instead of just scaling about the view center).
+    fn zoom_at(&mut self, pointer: Pos2, factor: f64) {
+        let rect = self.image_rect;
+        let fx = ((pointer.x - rect.min.x) / rect.width()) as f64;
+        let fy = ((pointer.y - rect.min.y) / rect.height()) as f64;
+
+        let bounds = self.bounds();
+        let (x_min, x_max) = bounds.x;
+        let (y_min, y_max) = bounds.y;
+        let target_x = x_min + fx * (x_max - x_min);
+        let target_y = y_min + fy * (y_max - y_min);
+
+        self.zoom *= factor;
+
+        self.center_x = target_x + self.zoom * (1.0 - 2.0 * fx);
+        self.center_y = target_y + self.zoom * (1.0 - 2.0 * fy);
     }
 }
 
@@ -78,17 +267,91 @@ impl App for MandelbrotApp {
         CentralPanel::default().show(ctx, |ui| {
             ui.heading("Solo Mandelbrot Set");
 
-            // Handle mouse wheel for zooming
+            self.poll_renderer(ctx);
+
+            ui.add_enabled(
+                self.gpu_available,
+                egui::Checkbox::new(&mut self.use_gpu, "Render on GPU"),
+            );
+            if !self.gpu_available {
+                self.use_gpu = false;
+            }
+
+            let mut colormap_changed = false;
+            egui::ComboBox::from_label("Colormap")
+                .selected_text(self.colormap.label())
+                .show_ui(ui, |ui| {
+                    for option in Colormap::ALL {
+                        if ui
+                            .selectable_value(&mut self.colormap, option, option.label())
+                            .changed()
+                        {
+                            colormap_changed = true;
+                        }
+                    }
+                });
+            if colormap_changed {
+                self.regenerate();
+            }
+
+            let mut julia_changed = ui.checkbox(&mut self.julia_mode, "Julia mode").changed();
+            if self.julia_mode {
+                let seed = self.julia_seed.get_or_insert(DEFAULT_JULIA_SEED);
+                ui.horizontal(|ui| {
+                    julia_changed |= ui
+                        .add(egui::Slider::new(&mut seed.re, -2.0..=2.0).text("seed re"))
+                        .changed();
+                    julia_changed |= ui
+                        .add(egui::Slider::new(&mut seed.im, -2.0..=2.0).text("seed im"))
+                        .changed();
+                    if ui.button("Reset seed").clicked() {
+                        *seed = DEFAULT_JULIA_SEED;
+                        julia_changed = true;
+                    }
+                });
+            }
+            if julia_changed {
+                self.regenerate();
+            }
+
+            // Adjustable iteration limit: a slider, and T/G to double/halve it.
+            let mut iter_changed = ui
+                .add(egui::Slider::new(&mut self.max_iter, 16..=4096).text("Max iterations"))
+                .changed();
+            ui.input(|i| {
+                if i.key_pressed(egui::Key::T) {
+                    self.max_iter = (self.max_iter * 2).min(4096);
+                    iter_changed = true;
+                }
+                if i.key_pressed(egui::Key::G) {
+                    self.max_iter = (self.max_iter / 2).max(16);
+                    iter_changed = true;
+                }
+            });
+            if iter_changed {
+                self.regenerate();
+            }
+
+            ui.label(format!(
+                "{:.1} ms/frame ({:.0} fps)",
+                self.last_frame_time.as_secs_f64() * 1000.0,
+                1.0 / self.last_frame_time.as_secs_f64().max(1e-6),
+            ));
+
+            // Handle mouse wheel for zooming, anchored on the cursor position
+            // so the point under it stays fixed instead of drifting.
             let scroll_delta = ctx.input(|i| i.raw_scroll_delta);
             if scroll_delta.y != 0.0 {
-                let zoom_factor = 1.1;
-                if scroll_delta.y > 0.0 {
-                    self.zoom /= zoom_factor; // Zoom in
-                } else {
-                    self.zoom *= zoom_factor; // Zoom out
+                if let Some(pointer) = ctx.pointer_hover_pos() {
+                    let zoom_factor = 1.1;
+                    let factor = if scroll_delta.y > 0.0 {
+                        1.0 / zoom_factor // Zoom in
+                    } else {
+                        zoom_factor // Zoom out
+                    };
+                    self.zoom_at(pointer, factor);
+                    self.request_regenerate(ctx);
                 }
-                self.generate_mandelbrot();
-                self.texture_handle = None; // Invalidate the texture
             }
 
             // Handle mouse dragging for panning
@@ -101,8 +364,7 @@ impl App for MandelbrotApp {
                             let delta_y = (pos.y - last_pos.y) as f64 / self.zoom;
                             self.center_x -= delta_x;
                             self.center_y -= delta_y;
-                            self.generate_mandelbrot();
-                            self.texture_handle = None; // Invalidate the texture
+                            self.request_regenerate(ctx);
                         }
                     }
                     self.last_mouse_pos = Some(pos);
@@ -113,16 +375,60 @@ impl App for MandelbrotApp {
                 self.last_mouse_pos = None;
             }
 
-            // Load the texture only if it's not already loaded or if the image has changed
-            if self.texture_handle.is_none() {
-                self.texture_handle =
-                    Some(ctx.load_texture("mandelbrot", self.image.clone(), Default::default()));
+            // Right-click-drag nudges the Julia seed, letting the user
+            // distort the fractal interactively while in Julia mode.
+            if self.julia_mode {
+                if ui.input(|i| i.pointer.button_down(PointerButton::Secondary)) {
+                    if let Some(pos) = ctx.pointer_interact_pos() {
+                        if let Some(last_pos) = self.last_seed_drag_pos {
+                            let seed = self.julia_seed.get_or_insert(DEFAULT_JULIA_SEED);
+                            seed.re += (pos.x - last_pos.x) as f64 / self.width as f64;
+                            seed.im += (pos.y - last_pos.y) as f64 / self.height as f64;
+                            self.request_regenerate(ctx);
+                        }
+                        self.last_seed_drag_pos = Some(pos);
+                    }
+                } else {
+                    self.last_seed_drag_pos = None;
+                }
             }
 
-            // Display the image
-            if let Some(texture_handle) = &self.texture_handle {
-                let pixels_per_point = ctx.pixels_per_point();
-                ui.add(Image::new(texture_handle).fit_to_original_size(pixels_per_point));
+            self.flush_pending_regenerate();
+
+            if self.use_gpu {
+                let (rect, _response) =
+                    ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+                self.image_rect = rect;
+                ui.painter().add(egui_wgpu::Callback::new_paint_callback(
+                    rect,
+                    MandelbrotCallback {
+                        center: (self.center_x, self.center_y),
+                        zoom: self.zoom,
+                        iters: self.max_iter,
+                        julia_seed: self.julia_mode.then(|| {
+                            let seed = self.julia_seed.unwrap_or(DEFAULT_JULIA_SEED);
+                            (seed.re, seed.im)
+                        }),
+                        colormap: self.colormap.shader_index(),
+                    },
+                ));
+            } else {
+                // Load the texture only if it's not already loaded or if the image has changed
+                if self.texture_handle.is_none() {
+                    self.texture_handle = Some(ctx.load_texture(
+                        "mandelbrot",
+                        self.image.clone(),
+                        Default::default(),
+                    ));
+                }
+
+                // Display the image
+                if let Some(texture_handle) = &self.texture_handle {
+                    let pixels_per_point = ctx.pixels_per_point();
+                    let response =
+                        ui.add(Image::new(texture_handle).fit_to_original_size(pixels_per_point));
+                    self.image_rect = response.rect;
+                }
             }
         });
     }
@@ -133,11 +439,12 @@ fn main() -> Result<(), Error> {
     let height = 600;
 
     let mut app = MandelbrotApp::new(width, height);
-    app.generate_mandelbrot();
+    app.regenerate();
 
     let native_options = NativeOptions {
         viewport: ViewportBuilder::default()
             .with_inner_size(Vec2::new(width as f32, height as f32)),
+        renderer: eframe::Renderer::Wgpu,
         vsync: true,
         multisampling: 0,
         depth_buffer: 0,
@@ -148,6 +455,59 @@ fn main() -> Result<(), Error> {
     eframe::run_native(
         "Solo Mandelbrot Set",
         native_options,
-        Box::new(|_cc| Ok(Box::new(app))),
+        Box::new(|cc| {
+            // Degrade to CPU-only instead of panicking if the wgpu backend
+            // couldn't be initialized for some reason.
+            app.gpu_available = if let Some(wgpu_render_state) = cc.wgpu_render_state.as_ref() {
+                let renderer = MandelbrotRenderer::new(
+                    &wgpu_render_state.device,
+                    wgpu_render_state.target_format,
+                );
+                wgpu_render_state
+                    .renderer
+                    .write()
+                    .callback_resources
+                    .insert(renderer);
+                true
+            } else {
+                false
+            };
+            Box::new(app)
+        }),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target_under_pointer(app: &MandelbrotApp, pointer: Pos2) -> (f64, f64) {
+        let rect = app.image_rect;
+        let fx = ((pointer.x - rect.min.x) / rect.width()) as f64;
+        let fy = ((pointer.y - rect.min.y) / rect.height()) as f64;
+        let bounds = app.bounds();
+        (
+            bounds.x.0 + fx * (bounds.x.1 - bounds.x.0),
+            bounds.y.0 + fy * (bounds.y.1 - bounds.y.0),
+        )
+    }
+
+    #[test]
+    fn zoom_at_keeps_cursor_point_fixed() {
+        let mut app = MandelbrotApp::new(800, 600);
+        let pointer = Pos2::new(200.0, 150.0);
+        let before = target_under_pointer(&app, pointer);
+
+        app.zoom_at(pointer, 0.5);
+
+        let after = target_under_pointer(&app, pointer);
+        assert!((before.0 - after.0).abs() < 1e-9);
+        assert!((before.1 - after.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn smooth_escape_is_none_once_max_iter_reached() {
+        assert_eq!(smooth_escape(256, Complex::new(0.0, 0.0), 256), None);
+        assert!(smooth_escape(10, Complex::new(3.0, 0.0), 256).is_some());
+    }
+}
@@ -0,0 +1,107 @@
+// Row-tile rendering on a background thread pool, streamed back over a
+// channel and tagged by generation so stale renders are discarded on
+// arrival.
+
+use crate::colormap::Colormap;
+use crate::{color_phase, julia, mandelbrot, smooth_escape, Bounds};
+use egui::Color32;
+use num_complex::Complex;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+const ROWS_PER_TILE: usize = 32;
+
+// Parameters for one render, captured by value for the background thread.
+#[derive(Clone, Copy)]
+pub struct ViewParams {
+    pub width: usize,
+    pub height: usize,
+    pub bounds: Bounds,
+    pub colormap: Colormap,
+    pub max_iter: u32,
+    pub julia_seed: Option<Complex<f64>>,
+}
+
+// A horizontal strip of rendered pixels, tagged with its generation.
+pub struct Tile {
+    pub generation: u64,
+    pub y_start: usize,
+    pub rows: usize,
+    pub pixels: Vec<Color32>,
+}
+
+// Hands row-tiles off to a rayon thread pool and streams them back over a
+// channel as they finish.
+pub struct ProgressiveRenderer {
+    generation: Arc<AtomicU64>,
+    sender: Sender<Tile>,
+    receiver: Receiver<Tile>,
+}
+
+impl ProgressiveRenderer {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            generation: Arc::new(AtomicU64::new(0)),
+            sender,
+            receiver,
+        }
+    }
+
+    // Supersedes any in-flight render and schedules `params`. Returns the
+    // generation id and the number of tiles it will produce.
+    pub fn submit(&self, params: ViewParams) -> (u64, usize) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let tile_count = params.height.div_ceil(ROWS_PER_TILE).max(1);
+        let sender = self.sender.clone();
+
+        std::thread::spawn(move || {
+            (0..tile_count).into_par_iter().for_each(|tile_idx| {
+                let y_start = tile_idx * ROWS_PER_TILE;
+                let rows = ROWS_PER_TILE.min(params.height - y_start);
+                let mut pixels = vec![Color32::BLACK; params.width * rows];
+
+                let (x_min, x_max) = params.bounds.x;
+                let (y_min, y_max) = params.bounds.y;
+                for row in 0..rows {
+                    let y = y_start + row;
+                    let y_val = y_min + (y as f64 / params.height as f64) * (y_max - y_min);
+                    for x in 0..params.width {
+                        let x_val = x_min + (x as f64 / params.width as f64) * (x_max - x_min);
+                        let c = Complex::new(x_val, y_val);
+
+                        let (i, z) = match params.julia_seed {
+                            Some(seed) => julia(c, seed, params.max_iter),
+                            None => mandelbrot(c, params.max_iter),
+                        };
+                        pixels[row * params.width + x] =
+                            match smooth_escape(i, z, params.max_iter) {
+                                Some(mu) => params.colormap.sample(color_phase(mu)),
+                                None => Color32::BLACK,
+                            };
+                    }
+                }
+
+                // Tiles of a superseded generation are still sent and are
+                // filtered out by the caller; a send only fails once the
+                // renderer (and its receiver) is dropped, and then this tile
+                // is simply discarded.
+                let _ = sender.send(Tile {
+                    generation,
+                    y_start,
+                    rows,
+                    pixels,
+                });
+            });
+        });
+
+        (generation, tile_count)
+    }
+
+    // Drains all tiles currently queued from the background workers.
+    pub fn drain(&self) -> Vec<Tile> {
+        self.receiver.try_iter().collect()
+    }
+}